@@ -0,0 +1,140 @@
+//! Machine-readable record of a verification
+
+use crate::{
+    verify::verify_signature, ContentType, DigestedFile, FileType, KeyProvider, SignatureType,
+    SignatureVerifier, Su3, VerificationCheck, VerifyError,
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Outcome of a single check performed during verification
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(crate = "serde")]
+pub struct PolicyDecision {
+    /// Check that was performed
+    pub check: VerificationCheck,
+
+    /// Whether the check passed
+    pub passed: bool,
+}
+
+/// Record of everything checked while verifying a file
+///
+/// Checks that weren't reached because an earlier one failed are not recorded
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(crate = "serde")]
+pub struct AuditReport {
+    /// Signer ID (invalid UTF-8 sequences are replaced)
+    pub signer_id: String,
+
+    /// Version of the file (without the null bytes; invalid UTF-8 sequences are replaced)
+    pub version: String,
+
+    /// Signature type
+    pub signature_type: SignatureType,
+
+    /// File type
+    pub file_type: FileType,
+
+    /// Content type
+    pub content_type: ContentType,
+
+    /// Hex-encoded digest of the signed data (as computed by the [`SignatureVerifier`])
+    pub digest: String,
+
+    /// Hex-encoded SHA-256 digest of the key material the provider supplied for the key the signature has been made
+    /// with (eg. of the entire certificate for the [`CertDirKeyProvider`](crate::CertDirKeyProvider))
+    pub key_material_digest: Option<String>,
+
+    /// Checks in the order they were performed
+    pub decisions: Vec<PolicyDecision>,
+
+    /// Whether the signature is valid
+    pub verified: bool,
+
+    /// Reason the verification failed
+    pub error: Option<String>,
+
+    /// Start of the verification, including digesting the signed data (in seconds since the Unix epoch)
+    pub started_at: u64,
+
+    /// End of the verification (in seconds since the Unix epoch)
+    pub finished_at: u64,
+}
+
+impl Su3<'_> {
+    /// Verify the signature of the file and record every check
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case the signed data can't be serialised or digested
+    pub async fn verify_audited<P, V>(
+        &self,
+        provider: &P,
+        verifier: &V,
+    ) -> Result<AuditReport, VerifyError>
+    where
+        P: KeyProvider,
+        V: SignatureVerifier,
+    {
+        let file = self.digest(verifier)?;
+        Ok(file.verify_audited(provider, verifier).await)
+    }
+}
+
+impl DigestedFile {
+    /// Verify the signature of a digested (eg. streamed) file and record every check
+    pub async fn verify_audited<P, V>(&self, provider: &P, verifier: &V) -> AuditReport
+    where
+        P: KeyProvider,
+        V: SignatureVerifier,
+    {
+        let mut decisions = Vec::new();
+        let mut record = |check, passed| decisions.push(PolicyDecision { check, passed });
+
+        let header = &self.header;
+        let signer_id = header.signer_id();
+        record(VerificationCheck::SignerId, signer_id.is_ok());
+        let result = match signer_id {
+            Ok(signer_id) => verify_signature(self, signer_id, provider, verifier, record).await,
+            Err(err) => Err(VerifyError::InvalidSignerId(err)),
+        };
+
+        AuditReport {
+            signer_id: String::from_utf8_lossy(&header.raw_signer_id).into_owned(),
+            version: String::from_utf8_lossy(&header.raw_version)
+                .trim_matches(|r#char| char == '\0')
+                .to_string(),
+            signature_type: header.signature_type.clone(),
+            file_type: header.file_type.clone(),
+            content_type: header.content_type.clone(),
+            digest: hex(&self.digest),
+            key_material_digest: result
+                .as_ref()
+                .ok()
+                .map(|public_key| hex(&Sha256::digest(&public_key.data))),
+            decisions,
+            verified: result.is_ok(),
+            error: result.err().map(|err| err.to_string()),
+            started_at: unix_timestamp(self.started_at),
+            finished_at: unix_timestamp(SystemTime::now()),
+        }
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter()
+        .fold(String::with_capacity(data.len() * 2), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
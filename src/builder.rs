@@ -0,0 +1,397 @@
+//! Builders for the different content types
+
+use crate::{ContentType, FileType, HexPreview, SignatureType, Su3, MIN_VERSION_LENGTH};
+use deku::DekuUpdate;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+#[cfg(feature = "zip")]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Magic bytes at the start of every ZIP file
+const ZIP_MAGIC: &[u8; 4] = b"PK\x03\x04";
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+fn gzip(data: &[u8], buffer: &mut Vec<u8>) -> io::Result<()> {
+    buffer.clear();
+    let mut gz = GzEncoder::new(buffer, Compression::default());
+    gz.write_all(data)?;
+    gz.finish()?;
+    Ok(())
+}
+
+/// Fields shared by all builders
+#[derive(Clone)]
+struct Metadata {
+    signature_type: SignatureType,
+    raw_version: Vec<u8>,
+    raw_signer_id: Vec<u8>,
+}
+
+impl fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metadata")
+            .field("signature_type", &self.signature_type)
+            .field("raw_version", &HexPreview(&self.raw_version))
+            .field("raw_signer_id", &HexPreview(&self.raw_signer_id))
+            .finish()
+    }
+}
+
+impl Metadata {
+    fn new(signer_id: String, version: String) -> Self {
+        let mut raw_version = version.into_bytes();
+        raw_version.resize(raw_version.len().max(MIN_VERSION_LENGTH.into()), 0);
+
+        Self {
+            signature_type: SignatureType::default(),
+            raw_version,
+            raw_signer_id: signer_id.into_bytes(),
+        }
+    }
+
+    fn su3<'a>(
+        &'a self,
+        file_type: FileType,
+        content_type: ContentType,
+        raw_content: &'a [u8],
+    ) -> io::Result<Su3<'a>> {
+        if self.raw_signer_id.is_empty() {
+            return Err(invalid_input("signer ID is empty"));
+        }
+        if self.raw_signer_id.len() > u8::MAX.into() {
+            return Err(invalid_input("signer ID is longer than 255 bytes"));
+        }
+        if self.raw_version.len() > u8::MAX.into() {
+            return Err(invalid_input("version is longer than 255 bytes"));
+        }
+
+        let mut su3 = Su3 {
+            signature_type: self.signature_type.clone(),
+            file_type,
+            content_type,
+            raw_version: &self.raw_version,
+            raw_signer_id: &self.raw_signer_id,
+            raw_content,
+            ..Su3::default()
+        };
+        su3.update()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(su3)
+    }
+}
+
+/// Implement the constructor and the setters for the metadata shared by all builders
+macro_rules! impl_metadata {
+    ($builder:ident, $description:literal, $($field:ident),*) => {
+        impl $builder {
+            #[doc = concat!("Construct a new builder for ", $description)]
+            pub fn new<S, V>(signer_id: S, version: V) -> Self
+            where
+                S: Into<String>,
+                V: Into<String>,
+            {
+                Self {
+                    metadata: Metadata::new(signer_id.into(), version.into()),
+                    $($field: Default::default()),*
+                }
+            }
+
+            /// Sign the file with the signature type
+            #[must_use]
+            pub fn with_signature_type(mut self, signature_type: SignatureType) -> Self {
+                self.metadata.signature_type = signature_type;
+                self
+            }
+        }
+    };
+}
+
+/// Whether the root element of the XML document is an Atom `feed` element (optionally with a namespace prefix)
+fn is_atom_feed(document: &str) -> bool {
+    let mut rest = document.trim_start_matches('\u{feff}');
+    loop {
+        rest = rest.trim_start();
+        // Skip the XML declaration, processing instructions, comments and the document type declaration
+        let (prefix, suffix) = if rest.starts_with("<?") {
+            ("<?", "?>")
+        } else if rest.starts_with("<!--") {
+            ("<!--", "-->")
+        } else if rest.starts_with("<!DOCTYPE") {
+            ("<!DOCTYPE", ">")
+        } else {
+            break;
+        };
+
+        match rest[prefix.len()..].find(suffix) {
+            Some(end) => rest = &rest[prefix.len() + end + suffix.len()..],
+            None => return false,
+        }
+    }
+
+    let Some(element) = rest.strip_prefix('<') else {
+        return false;
+    };
+    let Some(end) =
+        element.find(|r#char: char| r#char.is_whitespace() || matches!(r#char, '>' | '/'))
+    else {
+        return false;
+    };
+
+    match element[..end].split_once(':') {
+        Some((prefix, name)) => !prefix.is_empty() && name == "feed",
+        None => &element[..end] == "feed",
+    }
+}
+
+/// Builder for news feeds (GZ compressed Atom XML)
+#[derive(Clone)]
+pub struct NewsFeedBuilder {
+    metadata: Metadata,
+    feed: Vec<u8>,
+}
+
+impl fmt::Debug for NewsFeedBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NewsFeedBuilder")
+            .field("metadata", &self.metadata)
+            .field("feed", &HexPreview(&self.feed))
+            .finish()
+    }
+}
+
+impl_metadata!(NewsFeedBuilder, "a news feed", feed);
+
+impl NewsFeedBuilder {
+    /// Set the uncompressed Atom XML feed
+    #[must_use]
+    pub fn with_feed<F>(mut self, feed: F) -> Self
+    where
+        F: Into<Vec<u8>>,
+    {
+        self.feed = feed.into();
+        self
+    }
+
+    /// Compress the feed into the buffer and return the unsigned SU3 file (see [`Su3::with_signature`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the root element of the feed is not an Atom `feed` element, the metadata is invalid or the compression fails
+    pub fn build<'a>(&'a self, buffer: &'a mut Vec<u8>) -> io::Result<Su3<'a>> {
+        let feed = std::str::from_utf8(&self.feed)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        if !is_atom_feed(feed) {
+            return Err(invalid_input(
+                "root element of the feed is not an Atom feed element",
+            ));
+        }
+
+        gzip(&self.feed, buffer)?;
+        self.metadata
+            .su3(FileType::XmlGz, ContentType::NewsFeed, buffer)
+    }
+}
+
+/// Builder for blocklist feeds (GZ compressed list of entries, one per line)
+#[derive(Clone)]
+pub struct BlocklistFeedBuilder {
+    metadata: Metadata,
+    entries: Vec<String>,
+}
+
+impl fmt::Debug for BlocklistFeedBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlocklistFeedBuilder")
+            .field("metadata", &self.metadata)
+            .field("entries", &format_args!("<{} entries>", self.entries.len()))
+            .finish()
+    }
+}
+
+impl_metadata!(BlocklistFeedBuilder, "a blocklist feed", entries);
+
+impl BlocklistFeedBuilder {
+    /// Add an entry (eg. an IP address or a router hash) to the blocklist
+    #[must_use]
+    pub fn with_entry<E>(mut self, entry: E) -> Self
+    where
+        E: Into<String>,
+    {
+        self.entries.push(entry.into());
+        self
+    }
+
+    /// Add multiple entries to the blocklist
+    #[must_use]
+    pub fn with_entries<I>(mut self, entries: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.entries.extend(entries.into_iter().map(Into::into));
+        self
+    }
+
+    /// Compress the entries into the buffer and return the unsigned SU3 file (see [`Su3::with_signature`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case an entry is empty or spans multiple lines,
+    /// the metadata is invalid or the compression fails
+    pub fn build<'a>(&'a self, buffer: &'a mut Vec<u8>) -> io::Result<Su3<'a>> {
+        let mut list = String::new();
+        for entry in &self.entries {
+            if entry.trim().is_empty() || entry.contains(['\n', '\r']) {
+                return Err(invalid_input(
+                    "blocklist entry is empty or spans multiple lines",
+                ));
+            }
+
+            list.push_str(entry);
+            list.push('\n');
+        }
+
+        gzip(list.as_bytes(), buffer)?;
+        self.metadata
+            .su3(FileType::TxtGz, ContentType::BlocklistFeed, buffer)
+    }
+}
+
+/// Builder for plugins (ZIP archive of the plugin directory)
+#[cfg(feature = "zip")]
+#[derive(Clone, Debug)]
+pub struct PluginBuilder {
+    metadata: Metadata,
+    directory: PathBuf,
+}
+
+#[cfg(feature = "zip")]
+impl_metadata!(PluginBuilder, "a plugin", directory);
+
+#[cfg(feature = "zip")]
+impl PluginBuilder {
+    /// Set the plugin directory (has to contain the `plugin.config` file)
+    #[must_use]
+    pub fn with_directory<P>(mut self, directory: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.directory = directory.into();
+        self
+    }
+
+    /// Archive the plugin directory into the buffer and return the unsigned SU3 file (see [`Su3::with_signature`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the directory doesn't contain a `plugin.config` or contains symlinks,
+    /// the metadata is invalid or reading and archiving the directory fails
+    pub fn build<'a>(&'a self, buffer: &'a mut Vec<u8>) -> io::Result<Su3<'a>> {
+        if !self.directory.join("plugin.config").is_file() {
+            return Err(invalid_input("plugin directory is missing plugin.config"));
+        }
+
+        buffer.clear();
+        let mut zip = zip::ZipWriter::new(io::Cursor::new(&mut *buffer));
+        archive_directory(&mut zip, &self.directory, "")?;
+        zip.finish()?;
+
+        self.metadata
+            .su3(FileType::Zip, ContentType::Plugin, buffer)
+    }
+}
+
+/// Recursively add the contents of the directory to the archive (sorted by name)
+#[cfg(feature = "zip")]
+fn archive_directory<W>(
+    zip: &mut zip::ZipWriter<W>,
+    directory: &Path,
+    prefix: &str,
+) -> io::Result<()>
+where
+    W: Write + io::Seek,
+{
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut entries = fs::read_dir(directory)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        let file_name = entry.file_name();
+        let file_name = file_name
+            .to_str()
+            .ok_or_else(|| invalid_input("plugin file name is not valid UTF-8"))?;
+        let name = format!("{prefix}{file_name}");
+
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            // Not followed since they could point outside of the plugin directory
+            return Err(invalid_input("plugin directory contains a symlink"));
+        }
+
+        if file_type.is_dir() {
+            zip.add_directory(name.as_str(), options)?;
+            archive_directory(zip, &entry.path(), &format!("{name}/"))?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut fs::File::open(entry.path())?, zip)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Builder for router updates (wrapping an `i2pupdate.zip`)
+#[derive(Clone)]
+pub struct RouterUpdateBuilder {
+    metadata: Metadata,
+    update: Vec<u8>,
+}
+
+impl fmt::Debug for RouterUpdateBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouterUpdateBuilder")
+            .field("metadata", &self.metadata)
+            .field("update", &HexPreview(&self.update))
+            .finish()
+    }
+}
+
+impl_metadata!(RouterUpdateBuilder, "a router update", update);
+
+impl RouterUpdateBuilder {
+    /// Set the contents of the `i2pupdate.zip` file
+    #[must_use]
+    pub fn with_update<U>(mut self, update: U) -> Self
+    where
+        U: Into<Vec<u8>>,
+    {
+        self.update = update.into();
+        self
+    }
+
+    /// Return the unsigned SU3 file (see [`Su3::with_signature`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the update is not a ZIP file or the metadata is invalid
+    pub fn build(&self) -> io::Result<Su3<'_>> {
+        if !self.update.starts_with(ZIP_MAGIC) {
+            return Err(invalid_input("router update is not a ZIP file"));
+        }
+
+        self.metadata
+            .su3(FileType::Zip, ContentType::RouterUpdate, &self.update)
+    }
+}
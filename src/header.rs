@@ -0,0 +1,124 @@
+//! Decoder for the fixed-size part of the SU3 header
+
+#[cfg(any(feature = "std", feature = "heapless"))]
+use crate::{ContentType, FileType, SignatureType, HEADER_LENGTH, MAGIC, MIN_VERSION_LENGTH};
+use core::fmt;
+
+/// Error returned by the en-/decoders that don't go through deku
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// A field doesn't fit into its buffer
+    Capacity,
+
+    /// The input ended before the file was complete
+    Truncated,
+
+    /// The input doesn't start with the SU3 magic bytes
+    InvalidMagic,
+
+    /// The file format version isn't supported
+    UnsupportedFormatVersion(u8),
+
+    /// The version field is shorter than 16 bytes
+    InvalidVersionLength(u8),
+
+    /// Unknown signature type
+    UnknownSignatureType(u16),
+
+    /// Unknown file type
+    UnknownFileType(u8),
+
+    /// Unknown content type
+    UnknownContentType(u8),
+
+    /// The content length is so large that the length of the file overflows
+    InvalidContentLength(u64),
+
+    /// The signature length doesn't match the signature type
+    InvalidSignatureLength(u16),
+
+    /// The input continues after the end of the file (amount of trailing bytes)
+    TrailingData(usize),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Capacity => f.write_str("field exceeds buffer capacity"),
+            Self::Truncated => f.write_str("input is truncated"),
+            Self::InvalidMagic => f.write_str("invalid magic bytes"),
+            Self::UnsupportedFormatVersion(version) => {
+                write!(f, "unsupported format version {version}")
+            }
+            Self::InvalidVersionLength(length) => write!(f, "invalid version length {length}"),
+            Self::UnknownSignatureType(id) => write!(f, "unknown signature type {id:#06x}"),
+            Self::UnknownFileType(id) => write!(f, "unknown file type {id:#04x}"),
+            Self::UnknownContentType(id) => write!(f, "unknown content type {id:#04x}"),
+            Self::InvalidContentLength(length) => write!(f, "invalid content length {length}"),
+            Self::InvalidSignatureLength(length) => write!(f, "invalid signature length {length}"),
+            Self::TrailingData(length) => write!(f, "{length} trailing bytes after the file"),
+        }
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// Fields of the fixed-size header
+#[cfg(any(feature = "std", feature = "heapless"))]
+pub(crate) struct FixedHeader {
+    pub signature_type: SignatureType,
+    pub signature_length: u16,
+    pub version_length: u8,
+    pub signer_id_length: u8,
+    pub content_length: u64,
+    pub file_type: FileType,
+    pub content_type: ContentType,
+}
+
+#[cfg(any(feature = "std", feature = "heapless"))]
+impl FixedHeader {
+    /// Decode the fixed-size header
+    pub fn decode(header: &[u8; HEADER_LENGTH]) -> Result<Self, CodecError> {
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(CodecError::InvalidMagic);
+        }
+        if header[7] != 0 {
+            return Err(CodecError::UnsupportedFormatVersion(header[7]));
+        }
+
+        let signature_type = u16::from_be_bytes([header[8], header[9]]);
+        let signature_type =
+            SignatureType::try_from(signature_type).map_err(CodecError::UnknownSignatureType)?;
+
+        let version_length = header[13];
+        if version_length < MIN_VERSION_LENGTH {
+            return Err(CodecError::InvalidVersionLength(version_length));
+        }
+
+        let signature_length = u16::from_be_bytes([header[10], header[11]]);
+        let signer_id_length = header[15];
+
+        let mut content_length = [0; 8];
+        content_length.copy_from_slice(&header[16..24]);
+        let content_length = u64::from_be_bytes(content_length);
+
+        let overhead = HEADER_LENGTH as u64
+            + u64::from(version_length)
+            + u64::from(signer_id_length)
+            + u64::from(signature_length);
+        if content_length.checked_add(overhead).is_none() {
+            return Err(CodecError::InvalidContentLength(content_length));
+        }
+
+        Ok(Self {
+            signature_type,
+            signature_length,
+            version_length,
+            signer_id_length,
+            content_length,
+            file_type: FileType::try_from(header[25]).map_err(CodecError::UnknownFileType)?,
+            content_type: ContentType::try_from(header[27])
+                .map_err(CodecError::UnknownContentType)?,
+        })
+    }
+}
@@ -4,38 +4,118 @@
 //! # Examples
 //!
 //! ```
+//! # #[cfg(feature = "std")] {
 //! # use deku::DekuContainerRead;
 //! # use su3::Su3;
 //! # let su3_data = include_bytes!("../assets/meeh_i2pseeds.su3");
 //! let (_, parsed_su3) = Su3::from_bytes((su3_data, 0)).expect("Failed to parse SU3 file");
 //! let content = parsed_su3.content().expect("Failed to decompress content");
+//! # }
 //! ```
 //!
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(missing_docs, rust_2018_idioms, unsafe_code)]
 #![warn(clippy::all, clippy::pedantic)]
+// Triggered by the code generated by the `DekuRead` derive
+#![allow(clippy::manual_div_ceil)]
 
+#[cfg(feature = "std")]
 pub use deku;
 
+#[cfg(any(feature = "std", feature = "heapless"))]
+use core::fmt;
+#[cfg(feature = "std")]
 use deku::{DekuContainerWrite, DekuEnumExt, DekuError, DekuRead, DekuUpdate, DekuWrite};
-use flate2::read::GzDecoder;
+#[cfg(feature = "std")]
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+#[cfg(feature = "std")]
 use std::{
     borrow::Cow,
-    io::{self, Read},
+    io::{self, Read, Write},
     str::{self, Utf8Error},
 };
 
-#[cfg(test)]
+#[cfg(feature = "audit")]
+mod audit;
+#[cfg(feature = "std")]
+mod builder;
+mod header;
+#[cfg(feature = "heapless")]
+mod no_alloc;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(all(test, feature = "std"))]
 mod tests;
+#[cfg(feature = "std")]
+mod verify;
+
+#[cfg(feature = "audit")]
+pub use self::audit::{AuditReport, PolicyDecision};
+#[cfg(feature = "zip")]
+pub use self::builder::PluginBuilder;
+#[cfg(feature = "std")]
+pub use self::builder::{BlocklistFeedBuilder, NewsFeedBuilder, RouterUpdateBuilder};
+pub use self::header::CodecError;
+#[cfg(feature = "heapless")]
+pub use self::no_alloc::{HeaplessError, Su3Heapless, MAX_SIGNATURE_LENGTH};
+#[cfg(feature = "std")]
+pub use self::stream::{CancellationToken, Cancelled, ContentReader, Header, Su3Reader};
+#[cfg(feature = "std")]
+pub use self::verify::{
+    CertDirKeyProvider, DigestedFile, KeyProvider, PublicKey, SignatureVerifier, VerificationCheck,
+    VerifyError,
+};
+
+/// Magic bytes at the start of every SU3 file
+pub const MAGIC: &[u8; 6] = b"I2Psu3";
+
+/// Length of the fixed-size header (in bytes; everything preceding the version field)
+pub const HEADER_LENGTH: usize = 40;
 
 /// Minimum length of the version field
 pub const MIN_VERSION_LENGTH: u8 = 16;
 
+/// Amount of bytes shown by the `Debug` representation of raw fields
+#[cfg(any(feature = "std", feature = "heapless"))]
+const DEBUG_PREVIEW_LENGTH: usize = 16;
+
+/// `Debug` representation of a byte slice showing its length and a hex preview of its first bytes
+#[cfg(any(feature = "std", feature = "heapless"))]
+pub(crate) struct HexPreview<'a>(pub &'a [u8]);
+
+#[cfg(any(feature = "std", feature = "heapless"))]
+impl fmt::Debug for HexPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes", self.0.len())?;
+        if !self.0.is_empty() {
+            f.write_str(": ")?;
+            for byte in self.0.iter().take(DEBUG_PREVIEW_LENGTH) {
+                write!(f, "{byte:02x}")?;
+            }
+            if self.0.len() > DEBUG_PREVIEW_LENGTH {
+                f.write_str("…")?;
+            }
+        }
+        f.write_str(">")
+    }
+}
+
 /// Content type
-#[derive(Clone, Debug, DekuRead, DekuWrite, PartialEq, Eq, PartialOrd, Ord)]
-#[deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u8")]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "std",
+    derive(DekuRead, DekuWrite),
+    deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u8")
+)]
+#[cfg_attr(
+    feature = "audit",
+    derive(serde::Serialize),
+    serde(crate = "serde", rename_all = "snake_case")
+)]
 pub enum ContentType {
     /// Unknown content type
+    #[default]
     Unknown = 0x00,
 
     /// Router update
@@ -54,17 +134,39 @@ pub enum ContentType {
     BlocklistFeed,
 }
 
-impl Default for ContentType {
-    fn default() -> Self {
-        Self::Unknown
+impl TryFrom<u8> for ContentType {
+    type Error = u8;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        let content_type = match id {
+            0x00 => Self::Unknown,
+            0x01 => Self::RouterUpdate,
+            0x02 => Self::Plugin,
+            0x03 => Self::ReseedData,
+            0x04 => Self::NewsFeed,
+            0x05 => Self::BlocklistFeed,
+            _ => return Err(id),
+        };
+
+        Ok(content_type)
     }
 }
 
 /// File type
-#[derive(Clone, Debug, DekuRead, DekuWrite, PartialEq, Eq, PartialOrd, Ord)]
-#[deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u8")]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "std",
+    derive(DekuRead, DekuWrite),
+    deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u8")
+)]
+#[cfg_attr(
+    feature = "audit",
+    derive(serde::Serialize),
+    serde(crate = "serde", rename_all = "snake_case")
+)]
 pub enum FileType {
     /// ZIP file
+    #[default]
     Zip = 0x00,
 
     /// XML file
@@ -86,17 +188,40 @@ pub enum FileType {
     Exe,
 }
 
-impl Default for FileType {
-    fn default() -> Self {
-        Self::Zip
+impl TryFrom<u8> for FileType {
+    type Error = u8;
+
+    fn try_from(id: u8) -> Result<Self, Self::Error> {
+        let file_type = match id {
+            0x00 => Self::Zip,
+            0x01 => Self::Xml,
+            0x02 => Self::Html,
+            0x03 => Self::XmlGz,
+            0x04 => Self::TxtGz,
+            0x05 => Self::Dmg,
+            0x06 => Self::Exe,
+            _ => return Err(id),
+        };
+
+        Ok(file_type)
     }
 }
 
 /// Signature type
-#[derive(Clone, Debug, DekuRead, DekuWrite, PartialEq, Eq, PartialOrd, Ord)]
-#[deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u16")]
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "std",
+    derive(DekuRead, DekuWrite),
+    deku(ctx = "endian: deku::ctx::Endian", endian = "endian", type = "u16")
+)]
+#[cfg_attr(
+    feature = "audit",
+    derive(serde::Serialize),
+    serde(crate = "serde", rename_all = "snake_case")
+)]
 pub enum SignatureType {
     /// DSA-SHA1
+    #[default]
     DsaSha1 = 0x0000,
 
     /// ECDSA-SHA256-P256
@@ -139,14 +264,31 @@ impl SignatureType {
     }
 }
 
-impl Default for SignatureType {
-    fn default() -> Self {
-        Self::DsaSha1
+impl TryFrom<u16> for SignatureType {
+    type Error = u16;
+
+    fn try_from(id: u16) -> Result<Self, Self::Error> {
+        let signature_type = match id {
+            0x0000 => Self::DsaSha1,
+            0x0001 => Self::EcdsaSha256P256,
+            0x0002 => Self::EcdsaSha384P384,
+            0x0003 => Self::EcdsaSha512P521,
+            0x0004 => Self::RsaSha2562048,
+            0x0005 => Self::RsaSha3843072,
+            0x0006 => Self::RsaSha5124096,
+            0x0008 => Self::EddsaSha512Ed25519ph,
+            _ => return Err(id),
+        };
+
+        Ok(signature_type)
     }
 }
 
 /// Typed representation of an SU3 file
-#[derive(Clone, Debug, Default, DekuRead, DekuWrite, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// The `Debug` representation only shows the lengths and a short hex preview of the raw fields
+#[cfg(feature = "std")]
+#[derive(Clone, Default, DekuRead, DekuWrite, PartialEq, Eq, PartialOrd, Ord)]
 #[deku(endian = "big", magic = b"I2Psu3")]
 pub struct Su3<'a> {
     /// Unused field
@@ -222,6 +364,33 @@ pub struct Su3<'a> {
     pub raw_signature: &'a [u8],
 }
 
+#[cfg(feature = "std")]
+impl fmt::Debug for Su3<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Su3")
+            .field("unused_0", &self.unused_0)
+            .field("format_version", &self.format_version)
+            .field("signature_type", &self.signature_type)
+            .field("signature_length", &self.signature_length)
+            .field("unused_1", &self.unused_1)
+            .field("version_length", &self.version_length)
+            .field("unused_2", &self.unused_2)
+            .field("signer_id_length", &self.signer_id_length)
+            .field("content_length", &self.content_length)
+            .field("unused_3", &self.unused_3)
+            .field("file_type", &self.file_type)
+            .field("unused_4", &self.unused_4)
+            .field("content_type", &self.content_type)
+            .field("unused_5", &HexPreview(&self.unused_5))
+            .field("raw_version", &HexPreview(self.raw_version))
+            .field("raw_signer_id", &HexPreview(self.raw_signer_id))
+            .field("raw_content", &HexPreview(self.raw_content))
+            .field("raw_signature", &HexPreview(self.raw_signature))
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
 impl<'a> Su3<'a> {
     /// Return the possibly decompressed representation of the content
     ///
@@ -246,6 +415,161 @@ impl<'a> Su3<'a> {
         Ok(content)
     }
 
+    /// Convert the content into the paired file type (`Xml` ↔ `XmlGz`) and write it into the buffer
+    ///
+    /// The format has no type for uncompressed text, so use [`Su3::plain_text`] and [`Su3::with_plain_text`]
+    /// to convert `TxtGz` content from and to plain text and [`Su3::recompress`] to recompress GZ compressed content.
+    ///
+    /// The content length is updated and the file is returned unsigned since the signature doesn't cover the new
+    /// content anymore (see [`Su3::with_signature`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the file types aren't paired or the (de-)compression fails
+    pub fn transcode<'b>(&self, file_type: FileType, buffer: &'b mut Vec<u8>) -> io::Result<Su3<'b>>
+    where
+        'a: 'b,
+    {
+        buffer.clear();
+        match (&self.file_type, &file_type) {
+            (FileType::Xml, FileType::XmlGz) => {
+                let mut gz = GzEncoder::new(&mut *buffer, Compression::default());
+                gz.write_all(self.raw_content)?;
+                gz.finish()?;
+            }
+            (FileType::XmlGz, FileType::Xml) => {
+                GzDecoder::new(self.raw_content).read_to_end(buffer)?;
+            }
+            (from, to) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("can't transcode {from:?} into {to:?}"),
+                ))
+            }
+        }
+
+        self.unsigned(file_type, buffer)
+    }
+
+    /// Decompress the `XmlGz` or `TxtGz` content and compress it again into the buffer
+    ///
+    /// The file is returned unsigned (see [`Su3::transcode`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the content isn't GZ compressed or the (de-)compression fails
+    pub fn recompress<'b>(&self, buffer: &'b mut Vec<u8>) -> io::Result<Su3<'b>>
+    where
+        'a: 'b,
+    {
+        if !matches!(self.file_type, FileType::XmlGz | FileType::TxtGz) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't recompress {:?}", self.file_type),
+            ));
+        }
+
+        buffer.clear();
+        let mut gz = GzEncoder::new(&mut *buffer, Compression::default());
+        gz.write_all(&self.content()?)?;
+        gz.finish()?;
+
+        self.unsigned(self.file_type.clone(), buffer)
+    }
+
+    /// Decompress the `TxtGz` content into plain text
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the file type isn't `TxtGz`, the decompression fails or the text isn't valid UTF-8
+    pub fn plain_text(&self) -> io::Result<String> {
+        if self.file_type != FileType::TxtGz {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't read {:?} as plain text", self.file_type),
+            ));
+        }
+
+        let mut text = String::new();
+        GzDecoder::new(self.raw_content).read_to_string(&mut text)?;
+        Ok(text)
+    }
+
+    /// Compress the plain text into the buffer and return an unsigned `TxtGz` file with the metadata of this one
+    ///
+    /// See [`Su3::with_signature`] for signing the returned file
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the compression fails
+    pub fn with_plain_text<'b>(&self, text: &str, buffer: &'b mut Vec<u8>) -> io::Result<Su3<'b>>
+    where
+        'a: 'b,
+    {
+        buffer.clear();
+        let mut gz = GzEncoder::new(&mut *buffer, Compression::default());
+        gz.write_all(text.as_bytes())?;
+        gz.finish()?;
+
+        self.unsigned(FileType::TxtGz, buffer)
+    }
+
+    /// Attach the signature to the file
+    ///
+    /// Files returned by the builders, [`Su3::transcode`] and [`Su3::with_plain_text`] are unsigned:
+    /// Their signature length already matches the signature type, but the signature itself is empty,
+    /// so they can't be parsed again when serialised as-is.
+    /// Sign the [`Su3::signed_data`] and attach the signature with this method before serialising them
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the signature length doesn't match the signature type
+    pub fn with_signature<'b>(&self, signature: &'b [u8]) -> io::Result<Su3<'b>>
+    where
+        'a: 'b,
+    {
+        if signature.len() != usize::from(self.signature_type.length()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "signature length doesn't match the signature type",
+            ));
+        }
+
+        Ok(Su3 {
+            signature_length: self.signature_type.length(),
+            raw_signature: signature,
+            ..self.clone()
+        })
+    }
+
+    /// Unsigned copy of the file with the new content
+    fn unsigned<'b>(&self, file_type: FileType, raw_content: &'b [u8]) -> io::Result<Su3<'b>>
+    where
+        'a: 'b,
+    {
+        let mut su3 = Su3 {
+            file_type,
+            raw_content,
+            raw_signature: &[],
+            ..self.clone()
+        };
+        su3.update()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(su3)
+    }
+
+    /// Data covered by the signature (the serialised file without the signature)
+    ///
+    /// # Errors
+    ///
+    /// Returns a deku error in case the serialisation fails
+    pub fn signed_data(&self) -> Result<Vec<u8>, DekuError> {
+        let mut signed_data = self.to_bytes()?;
+        signed_data.truncate(signed_data.len() - self.raw_signature.len());
+        Ok(signed_data)
+    }
+
     /// Signer ID in form of a string slice
     ///
     /// # Errors
@@ -0,0 +1,287 @@
+//! Owned SU3 representation that doesn't require an allocator
+
+#[cfg(feature = "std")]
+use crate::Su3;
+use crate::{
+    header::FixedHeader, CodecError, ContentType, FileType, HexPreview, SignatureType,
+    HEADER_LENGTH, MAGIC, MIN_VERSION_LENGTH,
+};
+use core::{
+    fmt,
+    str::{self, Utf8Error},
+};
+use heapless::{CapacityError, Vec};
+
+/// Error returned by [`Su3Heapless`]
+///
+/// Alias of the [`CodecError`] shared with the streaming decoder
+pub type HeaplessError = CodecError;
+
+/// Maximum length of a signature (in bytes; RSA-SHA512-4096)
+pub const MAX_SIGNATURE_LENGTH: usize = 512;
+
+/// Maximum length of the version and signer ID fields (their lengths are encoded as a single byte)
+const MAX_FIELD_LENGTH: usize = u8::MAX as usize;
+
+impl From<CapacityError> for HeaplessError {
+    fn from(_: CapacityError) -> Self {
+        Self::Capacity
+    }
+}
+
+/// SU3 file owning its fields in fixed-capacity buffers
+///
+/// `N` is the maximum content length. None of the methods of this type allocate, so with the default `std` feature
+/// disabled it can be used to construct and serialise small files (eg. blocklists) on targets without an allocator
+#[derive(Clone, PartialEq, Eq)]
+pub struct Su3Heapless<const N: usize> {
+    /// Signature type
+    pub signature_type: SignatureType,
+
+    /// File type
+    pub file_type: FileType,
+
+    /// Content type
+    pub content_type: ContentType,
+
+    /// Version (UTF-8 padded with null bytes)
+    pub raw_version: Vec<u8, MAX_FIELD_LENGTH>,
+
+    /// Signer ID (UTF-8 encoded; no padding)
+    pub raw_signer_id: Vec<u8, MAX_FIELD_LENGTH>,
+
+    /// Raw content
+    pub raw_content: Vec<u8, N>,
+
+    /// Signature
+    ///
+    /// Empty for files that haven't been signed yet
+    pub raw_signature: Vec<u8, MAX_SIGNATURE_LENGTH>,
+}
+
+impl<const N: usize> fmt::Debug for Su3Heapless<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Su3Heapless")
+            .field("signature_type", &self.signature_type)
+            .field("file_type", &self.file_type)
+            .field("content_type", &self.content_type)
+            .field("raw_version", &HexPreview(&self.raw_version))
+            .field("raw_signer_id", &HexPreview(&self.raw_signer_id))
+            .field("raw_content", &HexPreview(&self.raw_content))
+            .field("raw_signature", &HexPreview(&self.raw_signature))
+            .finish()
+    }
+}
+
+impl<const N: usize> Su3Heapless<N> {
+    /// Construct a new unsigned SU3 file
+    ///
+    /// The version is padded with null bytes to the minimum length of 16 bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case one of the fields exceeds its capacity
+    pub fn new(
+        signature_type: SignatureType,
+        file_type: FileType,
+        content_type: ContentType,
+        version: &str,
+        signer_id: &str,
+        content: &[u8],
+    ) -> Result<Self, HeaplessError> {
+        let mut raw_version = Vec::from_slice(version.as_bytes())?;
+        raw_version.resize(raw_version.len().max(MIN_VERSION_LENGTH.into()), 0)?;
+
+        Ok(Self {
+            signature_type,
+            file_type,
+            content_type,
+            raw_version,
+            raw_signer_id: Vec::from_slice(signer_id.as_bytes())?,
+            raw_content: Vec::from_slice(content)?,
+            raw_signature: Vec::new(),
+        })
+    }
+
+    /// Decode an SU3 file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case the input isn't a valid SU3 file (including a signature length that doesn't match
+    /// the signature type and trailing bytes after the signature) or one of the fields exceeds its capacity
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, HeaplessError> {
+        let header = bytes
+            .first_chunk::<HEADER_LENGTH>()
+            .ok_or(HeaplessError::Truncated)?;
+        let header = FixedHeader::decode(header)?;
+        if header.signature_length != header.signature_type.length() {
+            return Err(HeaplessError::InvalidSignatureLength(
+                header.signature_length,
+            ));
+        }
+        let content_length =
+            usize::try_from(header.content_length).map_err(|_| HeaplessError::Capacity)?;
+
+        let mut rest = &bytes[HEADER_LENGTH..];
+        let mut take = |length: usize| -> Result<&[u8], HeaplessError> {
+            if rest.len() < length {
+                return Err(HeaplessError::Truncated);
+            }
+            let (field, remaining) = rest.split_at(length);
+            rest = remaining;
+            Ok(field)
+        };
+
+        let su3 = Self {
+            signature_type: header.signature_type,
+            file_type: header.file_type,
+            content_type: header.content_type,
+            raw_version: Vec::from_slice(take(header.version_length.into())?)?,
+            raw_signer_id: Vec::from_slice(take(header.signer_id_length.into())?)?,
+            raw_content: Vec::from_slice(take(content_length)?)?,
+            raw_signature: Vec::from_slice(take(header.signature_length.into())?)?,
+        };
+        if !rest.is_empty() {
+            return Err(HeaplessError::TrailingData(rest.len()));
+        }
+
+        Ok(su3)
+    }
+
+    /// Length of the encoded file (in bytes)
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LENGTH
+            + self.raw_version.len()
+            + self.raw_signer_id.len()
+            + self.raw_content.len()
+            + self.raw_signature.len()
+    }
+
+    /// Encode the file into the buffer and return the amount of bytes written
+    ///
+    /// The signature length field is derived from the signature type, so files that haven't been signed yet
+    /// encode to exactly the data the signature has to cover
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case the buffer is too small, the version field is shorter than 16 bytes
+    /// or the signature is neither empty nor exactly as long as the signature type requires
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, HeaplessError> {
+        let signature_length = self.signature_type.length();
+        if !self.raw_signature.is_empty()
+            && self.raw_signature.len() != usize::from(signature_length)
+        {
+            // Bounded by the capacity of the buffer
+            #[allow(clippy::cast_possible_truncation)]
+            return Err(HeaplessError::InvalidSignatureLength(
+                self.raw_signature.len() as u16,
+            ));
+        }
+
+        let length = self.encoded_len();
+        let buf = buf.get_mut(..length).ok_or(HeaplessError::Capacity)?;
+
+        // Both lengths are bounded by the capacity of the buffers
+        #[allow(clippy::cast_possible_truncation)]
+        let version_length = self.raw_version.len() as u8;
+        #[allow(clippy::cast_possible_truncation)]
+        let signer_id_length = self.raw_signer_id.len() as u8;
+        if version_length < MIN_VERSION_LENGTH {
+            return Err(HeaplessError::InvalidVersionLength(version_length));
+        }
+
+        let (header, mut rest) = buf.split_at_mut(HEADER_LENGTH);
+        header.fill(0);
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        header[8..10].copy_from_slice(&(self.signature_type.clone() as u16).to_be_bytes());
+        header[10..12].copy_from_slice(&signature_length.to_be_bytes());
+        header[13] = version_length;
+        header[15] = signer_id_length;
+        header[16..24].copy_from_slice(&(self.raw_content.len() as u64).to_be_bytes());
+        header[25] = self.file_type.clone() as u8;
+        header[27] = self.content_type.clone() as u8;
+
+        for field in [
+            &self.raw_version[..],
+            &self.raw_signer_id,
+            &self.raw_content,
+            &self.raw_signature,
+        ] {
+            let (dest, remaining) = rest.split_at_mut(field.len());
+            dest.copy_from_slice(field);
+            rest = remaining;
+        }
+
+        Ok(length)
+    }
+
+    /// Encode the file into a fixed-capacity buffer
+    ///
+    /// # Errors
+    ///
+    /// See [`Su3Heapless::encode_into`]
+    pub fn to_vec<const M: usize>(&self) -> Result<Vec<u8, M>, HeaplessError> {
+        let mut buf = Vec::new();
+        buf.resize(self.encoded_len(), 0)?;
+        self.encode_into(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Borrow the file as an [`Su3`]
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn as_su3(&self) -> Su3<'_> {
+        // Both lengths are bounded by the capacity of the buffers
+        #[allow(clippy::cast_possible_truncation)]
+        Su3 {
+            signature_type: self.signature_type.clone(),
+            signature_length: self.signature_type.length(),
+            version_length: self.raw_version.len() as u8,
+            signer_id_length: self.raw_signer_id.len() as u8,
+            content_length: self.raw_content.len() as u64,
+            file_type: self.file_type.clone(),
+            content_type: self.content_type.clone(),
+            raw_version: &self.raw_version,
+            raw_signer_id: &self.raw_signer_id,
+            raw_content: &self.raw_content,
+            raw_signature: &self.raw_signature,
+            ..Su3::default()
+        }
+    }
+
+    /// Signer ID in form of a string slice
+    ///
+    /// # Errors
+    ///
+    /// An error occurs when the signer ID field of the file is not valid UTF-8
+    pub fn signer_id(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.raw_signer_id)
+    }
+
+    /// Version in form of a string slice (without the null bytes)
+    ///
+    /// # Errors
+    ///
+    /// An error occurs when the version field of the file is not valid UTF-8
+    pub fn version(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.raw_version).map(|version| version.trim_matches(|r#char| char == '\0'))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const N: usize> TryFrom<&Su3<'_>> for Su3Heapless<N> {
+    type Error = HeaplessError;
+
+    fn try_from(su3: &Su3<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signature_type: su3.signature_type.clone(),
+            file_type: su3.file_type.clone(),
+            content_type: su3.content_type.clone(),
+            raw_version: Vec::from_slice(su3.raw_version)?,
+            raw_signer_id: Vec::from_slice(su3.raw_signer_id)?,
+            raw_content: Vec::from_slice(su3.raw_content)?,
+            raw_signature: Vec::from_slice(su3.raw_signature)?,
+        })
+    }
+}
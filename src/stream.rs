@@ -0,0 +1,408 @@
+//! Streaming decoder for large SU3 files
+
+use crate::{
+    header::FixedHeader, ContentType, DigestedFile, FileType, SignatureType, SignatureVerifier,
+    Su3, HEADER_LENGTH,
+};
+use flate2::read::GzDecoder;
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    str::{self, Utf8Error},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+/// Size of the chunks the content is read in while computing the digest
+const DIGEST_CHUNK_LENGTH: usize = 64 * 1024;
+
+/// Token to cancel a running operation (eg. from another thread)
+///
+/// Clones share their state, so cancelling one clone cancels all of them
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Construct a new token that isn't cancelled yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of all operations observing this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the cancellation has been requested
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Error returned (wrapped into an IO error) when an operation has been cancelled through a [`CancellationToken`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Header of an SU3 file (everything preceding the content)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// Signature type
+    pub signature_type: SignatureType,
+
+    /// Signature length
+    pub signature_length: u16,
+
+    /// Content length (not including header or signature)
+    pub content_length: u64,
+
+    /// File type
+    pub file_type: FileType,
+
+    /// Content type
+    pub content_type: ContentType,
+
+    /// Version (UTF-8 padded with null bytes)
+    pub raw_version: Vec<u8>,
+
+    /// Signer ID (UTF-8 encoded; no padding)
+    pub raw_signer_id: Vec<u8>,
+}
+
+impl Header {
+    /// Length of the entire file (in bytes; including header and signature)
+    #[must_use]
+    pub fn file_length(&self) -> u64 {
+        // Saturating since the fields are public; decoded headers are guaranteed not to overflow
+        ((HEADER_LENGTH + self.raw_version.len() + self.raw_signer_id.len()) as u64)
+            .saturating_add(self.content_length)
+            .saturating_add(u64::from(self.signature_length))
+    }
+
+    /// Signer ID in form of a string slice
+    ///
+    /// # Errors
+    ///
+    /// An error occurs when the signer ID field of the file is not valid UTF-8
+    pub fn signer_id(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.raw_signer_id)
+    }
+
+    /// Version in form of a string slice (without the null bytes)
+    ///
+    /// # Errors
+    ///
+    /// An error occurs when the version field of the file is not valid UTF-8
+    pub fn version(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.raw_version).map(|version| version.trim_matches(|r#char| char == '\0'))
+    }
+}
+
+impl From<&Su3<'_>> for Header {
+    fn from(su3: &Su3<'_>) -> Self {
+        Self {
+            signature_type: su3.signature_type.clone(),
+            signature_length: su3.signature_length,
+            content_length: su3.content_length,
+            file_type: su3.file_type.clone(),
+            content_type: su3.content_type.clone(),
+            raw_version: su3.raw_version.to_vec(),
+            raw_signer_id: su3.raw_signer_id.to_vec(),
+        }
+    }
+}
+
+/// Streaming SU3 decoder
+///
+/// Reads the file piece by piece instead of requiring it to be fully loaded into memory.
+/// Every operation reports the amount of bytes processed to the progress callback and honours the cancellation token.
+///
+/// The signature can be verified while streaming with [`Su3Reader::verify`], which feeds the signed data
+/// into the digest of the [`SignatureVerifier`] instead of buffering the file.
+/// The header and the content can additionally be forwarded to an arbitrary sink with [`Su3Reader::with_signed_data`]
+pub struct Su3Reader<'a, R> {
+    inner: R,
+    header: Option<Header>,
+    raw_header: Vec<u8>,
+    remaining_content: u64,
+    processed: u64,
+    progress: Option<Box<dyn FnMut(u64, u64) + 'a>>,
+    cancellation: Option<CancellationToken>,
+    signed_data: Option<Box<dyn Write + 'a>>,
+}
+
+impl<'a, R> Su3Reader<'a, R>
+where
+    R: Read,
+{
+    /// Construct a new streaming decoder
+    ///
+    /// Nothing is read until the header is requested or one of the other operations is invoked
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            header: None,
+            raw_header: Vec::new(),
+            remaining_content: 0,
+            processed: 0,
+            progress: None,
+            cancellation: None,
+            signed_data: None,
+        }
+    }
+
+    /// Call the function with the amount of bytes processed and the total length of the file after every read
+    #[must_use]
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(u64, u64) + 'a,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Abort all operations with a [`Cancelled`] error once the token has been cancelled
+    #[must_use]
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Write all the data covered by the signature (header and content) to the sink while reading
+    ///
+    /// This has to be configured before the header is read, otherwise the sink won't receive the entire signed data
+    #[must_use]
+    pub fn with_signed_data<W>(mut self, signed_data: W) -> Self
+    where
+        W: Write + 'a,
+    {
+        self.signed_data = Some(Box::new(signed_data));
+        self
+    }
+
+    /// Amount of bytes read so far
+    #[must_use]
+    pub fn processed(&self) -> u64 {
+        self.processed
+    }
+
+    /// Header of the file (read on the first call)
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case reading fails, the header is invalid or the operation has been cancelled
+    pub fn header(&mut self) -> io::Result<&Header> {
+        if let Some(ref header) = self.header {
+            return Ok(header);
+        }
+
+        let mut raw_header = [0; HEADER_LENGTH];
+        self.read_signed(&mut raw_header)?;
+        let header = FixedHeader::decode(&raw_header)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut raw_version = vec![0; header.version_length.into()];
+        self.read_signed(&mut raw_version)?;
+        let mut raw_signer_id = vec![0; header.signer_id_length.into()];
+        self.read_signed(&mut raw_signer_id)?;
+
+        self.raw_header = [&raw_header[..], &raw_version, &raw_signer_id].concat();
+        self.remaining_content = header.content_length;
+        let header = self.header.insert(Header {
+            signature_type: header.signature_type,
+            signature_length: header.signature_length,
+            content_length: header.content_length,
+            file_type: header.file_type,
+            content_type: header.content_type,
+            raw_version,
+            raw_signer_id,
+        });
+        if let Some(ref mut progress) = self.progress {
+            progress(self.processed, header.file_length());
+        }
+
+        Ok(header)
+    }
+
+    /// Reader over the remaining raw content
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case reading the header fails
+    pub fn content_reader(&mut self) -> io::Result<ContentReader<'_, 'a, R>> {
+        self.header()?;
+        Ok(ContentReader(self))
+    }
+
+    /// Copy the remaining raw content into the writer and return the amount of bytes copied
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case reading or writing fails or the operation has been cancelled
+    pub fn extract<W>(&mut self, mut writer: W) -> io::Result<u64>
+    where
+        W: Write,
+    {
+        io::copy(&mut self.content_reader()?, &mut writer)
+    }
+
+    /// Copy the possibly decompressed content into the writer and return the amount of bytes written
+    ///
+    /// Note: This will only decompress the `TxtGz` and `XmlGz` types. ZIP files are not handled
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case reading, decompressing or writing fails or the operation has been cancelled
+    pub fn extract_decompressed<W>(&mut self, mut writer: W) -> io::Result<u64>
+    where
+        W: Write,
+    {
+        match self.header()?.file_type {
+            FileType::TxtGz | FileType::XmlGz => {
+                io::copy(&mut GzDecoder::new(self.content_reader()?), &mut writer)
+            }
+            _ => self.extract(writer),
+        }
+    }
+
+    /// Skip the remaining content, read the signature and return it
+    ///
+    /// The sink for the signed data is flushed afterwards
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case reading fails or the operation has been cancelled
+    pub fn finish(mut self) -> io::Result<Vec<u8>> {
+        io::copy(&mut self.content_reader()?, &mut io::sink())?;
+
+        let signature_length = self.header()?.signature_length;
+        let mut signature = vec![0; signature_length.into()];
+        self.check_cancellation()?;
+        self.inner.read_exact(&mut signature)?;
+        self.processed += u64::from(signature_length);
+        self.report_progress();
+
+        if let Some(ref mut signed_data) = self.signed_data {
+            signed_data.flush()?;
+        }
+
+        Ok(signature)
+    }
+
+    /// Copy the remaining raw content into the writer while feeding the signed data into the digest of the verifier,
+    /// then read the signature
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the content has already been (partially) read,
+    /// reading or writing fails or the operation has been cancelled
+    pub fn digest<W, V>(mut self, mut writer: W, verifier: &V) -> io::Result<DigestedFile>
+    where
+        W: Write,
+        V: SignatureVerifier,
+    {
+        let started_at = SystemTime::now();
+        let header = self.header()?.clone();
+        if self.remaining_content != header.content_length {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "content has already been read",
+            ));
+        }
+
+        let mut digest = verifier.digest(&header.signature_type);
+        digest.write_all(&self.raw_header)?;
+
+        let mut buf = vec![0; DIGEST_CHUNK_LENGTH];
+        loop {
+            let read = self.read_content(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+
+            digest.write_all(&buf[..read])?;
+            writer.write_all(&buf[..read])?;
+        }
+
+        Ok(DigestedFile {
+            header,
+            digest: verifier.finish(digest),
+            signature: self.finish()?,
+            started_at,
+        })
+    }
+
+    fn check_cancellation(&self) -> io::Result<()> {
+        match self.cancellation {
+            Some(ref cancellation) if cancellation.is_cancelled() => {
+                Err(io::Error::other(Cancelled))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn report_progress(&mut self) {
+        if let (Some(progress), Some(header)) = (&mut self.progress, &self.header) {
+            progress(self.processed, header.file_length());
+        }
+    }
+
+    fn read_signed(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.check_cancellation()?;
+        self.inner.read_exact(buf)?;
+        self.processed += buf.len() as u64;
+
+        if let Some(ref mut signed_data) = self.signed_data {
+            signed_data.write_all(buf)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_content(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining_content == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        self.check_cancellation()?;
+
+        let max_length = usize::try_from(self.remaining_content).unwrap_or(usize::MAX);
+        let length = buf.len().min(max_length);
+        let read = self.inner.read(&mut buf[..length])?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        if let Some(ref mut signed_data) = self.signed_data {
+            signed_data.write_all(&buf[..read])?;
+        }
+        self.remaining_content -= read as u64;
+        self.processed += read as u64;
+        self.report_progress();
+
+        Ok(read)
+    }
+}
+
+/// Reader over the raw content of a file streamed by an [`Su3Reader`]
+pub struct ContentReader<'r, 'a, R>(&'r mut Su3Reader<'a, R>);
+
+impl<R> Read for ContentReader<'_, '_, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read_content(buf)
+    }
+}
@@ -1,5 +1,96 @@
-use crate::{ContentType, FileType, Su3};
-use deku::DekuContainerRead;
+use crate::{
+    BlocklistFeedBuilder, CancellationToken, Cancelled, CertDirKeyProvider, CodecError,
+    ContentType, FileType, NewsFeedBuilder, PublicKey, RouterUpdateBuilder, SignatureType,
+    SignatureVerifier, Su3, Su3Reader, VerifyError, HEADER_LENGTH,
+};
+use deku::{DekuContainerRead, DekuContainerWrite};
+use std::{
+    collections::HashMap,
+    future::Future,
+    io,
+    pin::pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+/// Minimal executor driving a future to completion on the current thread
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future,
+{
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// FNV-1a digest (not cryptographically secure, but good enough for tests)
+struct Fnv1a(u64);
+
+impl io::Write for Fnv1a {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Verifier accepting signatures that are equal to the public key and were made over the expected data
+struct TestVerifier {
+    digest: Vec<u8>,
+}
+
+impl TestVerifier {
+    fn new(signed_data: &[u8]) -> Self {
+        let mut digest = Fnv1a(0xcbf2_9ce4_8422_2325);
+        io::Write::write_all(&mut digest, signed_data).unwrap();
+        Self {
+            digest: digest.0.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+impl SignatureVerifier for TestVerifier {
+    type Digest = Fnv1a;
+
+    fn digest(&self, _signature_type: &SignatureType) -> Self::Digest {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn finish(&self, digest: Self::Digest) -> Vec<u8> {
+        digest.0.to_be_bytes().to_vec()
+    }
+
+    fn verify(
+        &self,
+        _signature_type: &SignatureType,
+        public_key: &PublicKey,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        public_key.data == signature && digest == self.digest
+    }
+}
 
 /// Parse meeh I2P seeds file included from the Java I2P router tests
 #[test]
@@ -14,3 +105,562 @@ fn meeh_i2pseeds() {
     assert_eq!(parsed_su3.file_type, FileType::Zip);
     assert_eq!(parsed_su3.signer_id(), Ok("meeh@mail.i2p"));
 }
+
+/// Stream the meeh I2P seeds file while tracking the progress and the signed data
+#[test]
+fn meeh_i2pseeds_streaming() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let mut last_progress = (0, 0);
+    let mut signed_data = Vec::new();
+    let mut content = Vec::new();
+
+    let mut reader = Su3Reader::new(&raw_su3[..])
+        .with_progress(|processed, total| last_progress = (processed, total))
+        .with_signed_data(&mut signed_data);
+    assert_eq!(reader.header().unwrap().signer_id(), Ok("meeh@mail.i2p"));
+    reader
+        .extract(&mut content)
+        .expect("Failed to extract content");
+    let signature = reader.finish().expect("Failed to read signature");
+
+    assert_eq!(content, parsed_su3.raw_content);
+    assert_eq!(signature, parsed_su3.raw_signature);
+    assert_eq!(signed_data, raw_su3[..raw_su3.len() - signature.len()]);
+    assert_eq!(last_progress, (raw_su3.len() as u64, raw_su3.len() as u64));
+}
+
+/// Reject headers whose content length makes the file length overflow
+#[test]
+fn streaming_content_length_overflow() {
+    let mut raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3").to_vec();
+    raw_su3[16..24].copy_from_slice(&u64::MAX.to_be_bytes());
+
+    let mut reader = Su3Reader::new(&raw_su3[..]).with_progress(|_, _| {});
+    let err = reader.header().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.into_inner().unwrap().downcast_ref(),
+        Some(&CodecError::InvalidContentLength(u64::MAX))
+    );
+}
+
+/// Cancel streaming once the header has been read
+#[test]
+fn streaming_cancellation() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let cancellation = CancellationToken::new();
+
+    let mut reader = Su3Reader::new(&raw_su3[..]).with_cancellation(cancellation.clone());
+    reader.header().expect("Failed to read header");
+    cancellation.cancel();
+
+    let err = reader.extract(io::sink()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert_eq!(err.into_inner().unwrap().downcast_ref(), Some(&Cancelled));
+}
+
+/// Round-trip the meeh I2P seeds file through the allocation-free representation
+#[cfg(feature = "heapless")]
+#[test]
+fn heapless_roundtrip() {
+    use crate::Su3Heapless;
+
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let owned = Su3Heapless::<{ 128 * 1024 }>::from_slice(raw_su3)
+        .expect("Failed to decode I2Pseeds SU3 file");
+    assert_eq!(owned.as_su3(), parsed_su3);
+    assert_eq!(owned.signer_id(), Ok("meeh@mail.i2p"));
+
+    let mut buf = vec![0; owned.encoded_len()];
+    owned
+        .encode_into(&mut buf)
+        .expect("Failed to encode SU3 file");
+    assert_eq!(
+        buf,
+        parsed_su3.to_bytes().expect("Failed to serialise SU3 file")
+    );
+}
+
+/// Build, sign and decode a file on a device without an allocator
+#[cfg(feature = "heapless")]
+#[test]
+fn heapless_build() {
+    use crate::{HeaplessError, Su3Heapless};
+
+    let mut su3 = Su3Heapless::<32>::new(
+        SignatureType::EddsaSha512Ed25519ph,
+        FileType::TxtGz,
+        ContentType::BlocklistFeed,
+        "1700000000",
+        "blocklist@mail.i2p",
+        b"compressed entries",
+    )
+    .expect("Failed to construct SU3 file");
+    assert_eq!(su3.raw_version.len(), 16);
+    assert_eq!(su3.version(), Ok("1700000000"));
+
+    let signed_data = su3
+        .to_vec::<256>()
+        .expect("Failed to encode unsigned SU3 file");
+    assert_eq!(
+        signed_data[..],
+        su3.as_su3()
+            .signed_data()
+            .expect("Failed to serialise signed data")
+    );
+
+    su3.raw_signature = heapless::Vec::from_slice(&[7; 64]).unwrap();
+    let raw_su3 = su3
+        .to_vec::<256>()
+        .expect("Failed to encode signed SU3 file");
+    assert_eq!(&raw_su3[..signed_data.len()], &signed_data[..]);
+    assert_eq!(Su3Heapless::<32>::from_slice(&raw_su3), Ok(su3.clone()));
+    let (_, parsed_su3) = Su3::from_bytes((&raw_su3, 0)).expect("Failed to parse SU3 file");
+    assert_eq!(parsed_su3, su3.as_su3());
+
+    let mut trailing = raw_su3.clone();
+    trailing.push(0).unwrap();
+    assert_eq!(
+        Su3Heapless::<32>::from_slice(&trailing),
+        Err(HeaplessError::TrailingData(1))
+    );
+    let mut mismatched = raw_su3.clone();
+    mismatched[10..12].copy_from_slice(&10_u16.to_be_bytes());
+    assert_eq!(
+        Su3Heapless::<32>::from_slice(&mismatched),
+        Err(HeaplessError::InvalidSignatureLength(10))
+    );
+
+    assert_eq!(su3.to_vec::<64>(), Err(HeaplessError::Capacity));
+    assert_eq!(
+        Su3Heapless::<4>::new(
+            SignatureType::EddsaSha512Ed25519ph,
+            FileType::TxtGz,
+            ContentType::BlocklistFeed,
+            "1700000000",
+            "blocklist@mail.i2p",
+            b"compressed entries",
+        ),
+        Err(HeaplessError::Capacity)
+    );
+
+    su3.raw_signature.truncate(10);
+    assert_eq!(
+        su3.to_vec::<256>(),
+        Err(HeaplessError::InvalidSignatureLength(10))
+    );
+
+    su3.raw_signature.clear();
+    su3.raw_version.truncate(4);
+    assert_eq!(
+        su3.to_vec::<256>(),
+        Err(HeaplessError::InvalidVersionLength(4))
+    );
+}
+
+/// Resolve the keys of the signer through a key provider during verification
+#[test]
+fn verify_with_key_provider() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let public_key = PublicKey {
+        signature_type: Some(parsed_su3.signature_type.clone()),
+        data: parsed_su3.raw_signature.to_vec(),
+    };
+    let mut provider = HashMap::new();
+    provider.insert("meeh@mail.i2p".to_string(), vec![public_key.clone()]);
+    let verifier = TestVerifier::new(&raw_su3[..raw_su3.len() - parsed_su3.raw_signature.len()]);
+
+    let used_key = block_on(parsed_su3.verify(&provider, &verifier));
+    assert_eq!(used_key.ok(), Some(public_key.clone()));
+
+    let unknown = block_on(parsed_su3.verify(&HashMap::new(), &verifier));
+    assert!(matches!(unknown, Err(VerifyError::UnknownSigner)));
+
+    let missing_dir = CertDirKeyProvider::new("/nonexistent");
+    let unknown = block_on(parsed_su3.verify(&missing_dir, &verifier));
+    assert!(matches!(unknown, Err(VerifyError::UnknownSigner)));
+
+    let other_type = PublicKey {
+        signature_type: Some(SignatureType::EddsaSha512Ed25519ph),
+        ..public_key.clone()
+    };
+    provider.insert("meeh@mail.i2p".to_string(), vec![other_type]);
+    let unknown = block_on(parsed_su3.verify(&provider, &verifier));
+    assert!(matches!(unknown, Err(VerifyError::UnknownSigner)));
+
+    let other_key = PublicKey {
+        data: vec![0; public_key.data.len()],
+        ..public_key
+    };
+    provider.insert("meeh@mail.i2p".to_string(), vec![other_key]);
+    let invalid = block_on(parsed_su3.verify(&provider, &verifier));
+    assert!(matches!(invalid, Err(VerifyError::InvalidSignature)));
+}
+
+/// Read the certificates of signers from a directory
+#[test]
+fn cert_dir_key_provider() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let dir = std::env::temp_dir().join(format!("su3-cert-dir-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("certificates"))
+        .expect("Failed to create certificate directory");
+    std::fs::write(
+        dir.join("certificates/meeh_at_mail.i2p.crt"),
+        parsed_su3.raw_signature,
+    )
+    .expect("Failed to write certificate");
+    std::fs::write(dir.join("outside.crt"), b"outside").expect("Failed to write certificate");
+
+    let provider = CertDirKeyProvider::new(dir.join("certificates"));
+    let verifier = TestVerifier::new(&raw_su3[..raw_su3.len() - parsed_su3.raw_signature.len()]);
+    let used_key = block_on(parsed_su3.verify(&provider, &verifier));
+    assert_eq!(
+        used_key.ok(),
+        Some(PublicKey {
+            signature_type: None,
+            data: parsed_su3.raw_signature.to_vec(),
+        })
+    );
+
+    for signer_id in ["", "../outside", "..", ".hidden", "a/b", "a\\b", "a\0b"] {
+        let public_keys = provider.read_public_keys(signer_id);
+        assert!(
+            public_keys.expect("Failed to resolve keys").is_empty(),
+            "{signer_id:?} has not been rejected"
+        );
+    }
+
+    std::fs::remove_dir_all(dir).expect("Failed to remove certificate directory");
+}
+
+/// Verify the signature while streaming the file, without buffering the signed data
+#[test]
+fn verify_streaming() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let public_key = PublicKey {
+        signature_type: None,
+        data: parsed_su3.raw_signature.to_vec(),
+    };
+    let mut provider = HashMap::new();
+    provider.insert("meeh@mail.i2p".to_string(), vec![public_key.clone()]);
+    let verifier = TestVerifier::new(&raw_su3[..raw_su3.len() - parsed_su3.raw_signature.len()]);
+
+    let mut last_progress = (0, 0);
+    let mut content = Vec::new();
+    let reader = Su3Reader::new(&raw_su3[..]).with_progress(|processed, total| {
+        last_progress = (processed, total);
+    });
+    let used_key = block_on(reader.verify(&mut content, &provider, &verifier));
+    assert_eq!(used_key.ok(), Some(public_key));
+    assert_eq!(content, parsed_su3.raw_content);
+    assert_eq!(last_progress, (raw_su3.len() as u64, raw_su3.len() as u64));
+
+    let mut tampered = raw_su3.to_vec();
+    tampered[HEADER_LENGTH + 100] ^= 1;
+    let reader = Su3Reader::new(&tampered[..]);
+    let invalid = block_on(reader.verify(io::sink(), &provider, &verifier));
+    assert!(matches!(invalid, Err(VerifyError::InvalidSignature)));
+
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    let reader = Su3Reader::new(&raw_su3[..]).with_cancellation(cancellation);
+    let cancelled = block_on(reader.verify(io::sink(), &provider, &verifier));
+    assert!(matches!(cancelled, Err(VerifyError::Io(err)) if err.kind() == io::ErrorKind::Other));
+}
+
+/// Record the checks performed during verification in an audit report
+#[cfg(feature = "audit")]
+#[test]
+fn verify_audited() {
+    use crate::VerificationCheck;
+
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let public_key = PublicKey {
+        signature_type: None,
+        data: parsed_su3.raw_signature.to_vec(),
+    };
+    let mut provider = HashMap::new();
+    provider.insert("meeh@mail.i2p".to_string(), vec![public_key]);
+    let verifier = TestVerifier::new(&raw_su3[..raw_su3.len() - parsed_su3.raw_signature.len()]);
+
+    let report = block_on(parsed_su3.verify_audited(&provider, &verifier))
+        .expect("Failed to serialise signed data");
+    assert!(report.verified);
+    assert_eq!(report.signer_id, "meeh@mail.i2p");
+    assert!(report.key_material_digest.is_some());
+    assert!(report.decisions.iter().all(|decision| decision.passed));
+    assert_eq!(report.decisions.len(), 5);
+    assert!(report.started_at <= report.finished_at);
+
+    let before = std::time::SystemTime::now();
+    let file = Su3Reader::new(&raw_su3[..])
+        .digest(io::sink(), &verifier)
+        .expect("Failed to digest SU3 file");
+    assert!(file.started_at >= before);
+    let streamed_report = block_on(file.verify_audited(&provider, &verifier));
+    assert!(streamed_report.verified);
+    assert_eq!(streamed_report.digest, report.digest);
+
+    let report = block_on(parsed_su3.verify_audited(&HashMap::new(), &verifier))
+        .expect("Failed to serialise signed data");
+    assert!(!report.verified);
+    assert_eq!(
+        report
+            .decisions
+            .last()
+            .map(|decision| (decision.check, decision.passed)),
+        Some((VerificationCheck::KeyResolution, false))
+    );
+
+    let json = serde_json::to_value(&report).expect("Failed to serialise audit report");
+    assert_eq!(json["decisions"][2]["check"], "key_resolution");
+    assert_eq!(json["signature_type"], "rsa_sha5124096");
+    assert_eq!(json["content_type"], "reseed_data");
+}
+
+/// Compress XML content and convert it back
+#[test]
+fn transcode_xml() {
+    let feed = b"<?xml version='1.0' encoding='UTF-8'?><feed></feed>";
+    let version = [0; 16];
+    let signature = [0; 40];
+    let su3 = Su3 {
+        file_type: FileType::Xml,
+        content_type: ContentType::NewsFeed,
+        raw_version: &version,
+        raw_content: feed,
+        raw_signature: &signature,
+        ..Su3::default()
+    };
+
+    let mut compressed = Vec::new();
+    let compressed_su3 = su3
+        .transcode(FileType::XmlGz, &mut compressed)
+        .expect("Failed to compress content");
+    assert_eq!(compressed_su3.file_type, FileType::XmlGz);
+    assert_eq!(
+        compressed_su3.content_length,
+        compressed_su3.raw_content.len() as u64
+    );
+    assert!(compressed_su3.raw_signature.is_empty());
+
+    let signed_data = compressed_su3
+        .signed_data()
+        .expect("Failed to serialise signed data");
+    assert_eq!(
+        signed_data,
+        compressed_su3
+            .to_bytes()
+            .expect("Failed to serialise compressed file")
+    );
+    let signed_su3 = compressed_su3
+        .with_signature(&signature)
+        .expect("Failed to sign compressed file");
+    let raw_su3 = signed_su3
+        .to_bytes()
+        .expect("Failed to serialise compressed file");
+    assert_eq!(raw_su3[..signed_data.len()], signed_data);
+    let (_, parsed_su3) = Su3::from_bytes((&raw_su3, 0)).expect("Failed to parse compressed file");
+    assert_eq!(parsed_su3, signed_su3);
+    assert!(compressed_su3.with_signature(&signature[1..]).is_err());
+
+    let mut decompressed = Vec::new();
+    let decompressed_su3 = compressed_su3
+        .transcode(FileType::Xml, &mut decompressed)
+        .expect("Failed to decompress content");
+    assert_eq!(decompressed_su3.raw_content, feed);
+
+    let mut recompressed = Vec::new();
+    let recompressed_su3 = compressed_su3
+        .recompress(&mut recompressed)
+        .expect("Failed to recompress content");
+    assert_eq!(recompressed_su3.file_type, FileType::XmlGz);
+    assert_eq!(recompressed_su3.content().unwrap(), &feed[..]);
+
+    for file_type in [FileType::Xml, FileType::TxtGz] {
+        let err = su3.transcode(file_type, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+    let err = compressed_su3
+        .transcode(FileType::XmlGz, &mut Vec::new())
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = su3.recompress(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+/// Decompress text content and replace it with new plain text
+#[test]
+fn transcode_text() {
+    let version = [0; 16];
+    let su3 = Su3 {
+        content_type: ContentType::BlocklistFeed,
+        raw_version: &version,
+        ..Su3::default()
+    };
+
+    let mut buffer = Vec::new();
+    let text_su3 = su3
+        .with_plain_text("192.0.2.1\n", &mut buffer)
+        .expect("Failed to compress text");
+    assert_eq!(text_su3.file_type, FileType::TxtGz);
+    assert_eq!(text_su3.content_type, ContentType::BlocklistFeed);
+    assert_eq!(text_su3.content_length, text_su3.raw_content.len() as u64);
+    assert_eq!(text_su3.plain_text().unwrap(), "192.0.2.1\n");
+
+    let mut recompressed = Vec::new();
+    let recompressed_su3 = text_su3
+        .recompress(&mut recompressed)
+        .expect("Failed to recompress text");
+    assert_eq!(recompressed_su3.plain_text().unwrap(), "192.0.2.1\n");
+
+    let err = su3.plain_text().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+/// Only show the lengths and a preview of the raw fields in the `Debug` representation
+#[test]
+fn debug_redaction() {
+    let raw_su3 = include_bytes!("../assets/meeh_i2pseeds.su3");
+    let (_, parsed_su3) = Su3::from_bytes((raw_su3, 0)).expect("Failed to parse I2Pseeds SU3 file");
+
+    let debug = format!("{parsed_su3:?}");
+    assert!(debug.len() < 1024);
+    assert!(debug.contains(&format!(
+        "raw_content: <{} bytes: 504b0304",
+        parsed_su3.raw_content.len()
+    )));
+    assert!(debug.contains("raw_signer_id: <13 bytes: 6d656568406d61696c2e693270>"));
+
+    let public_key = PublicKey {
+        signature_type: None,
+        data: parsed_su3.raw_signature.to_vec(),
+    };
+    assert!(format!("{public_key:?}").contains("data: <512 bytes: "));
+
+    let builder = RouterUpdateBuilder::new("zzz@mail.i2p", "2.4.0").with_update(vec![0; 100_000]);
+    let debug = format!("{builder:?}");
+    assert!(debug.len() < 1024);
+    assert!(debug.contains("update: <100000 bytes: "));
+}
+
+/// Build news and blocklist feeds and parse them again
+#[test]
+fn build_feeds() {
+    let feed =
+        "<?xml version='1.0' encoding='UTF-8'?><feed xmlns='http://www.w3.org/2005/Atom'></feed>";
+    let builder = NewsFeedBuilder::new("news@mail.i2p", "1700000000").with_feed(feed);
+    let mut buffer = Vec::new();
+    let su3 = builder
+        .build(&mut buffer)
+        .expect("Failed to build news feed");
+    let signature = [0; 40];
+    let raw_su3 = su3
+        .with_signature(&signature)
+        .and_then(|su3| su3.to_bytes().map_err(io::Error::other))
+        .expect("Failed to serialise news feed");
+
+    let (_, parsed_su3) = Su3::from_bytes((&raw_su3, 0)).expect("Failed to parse news feed");
+    assert_eq!(parsed_su3.content_type, ContentType::NewsFeed);
+    assert_eq!(parsed_su3.file_type, FileType::XmlGz);
+    assert_eq!(parsed_su3.version(), Ok("1700000000"));
+    assert_eq!(parsed_su3.content().unwrap(), feed.as_bytes());
+
+    let builder = BlocklistFeedBuilder::new("blocklist@mail.i2p", "1700000000")
+        .with_entries(["192.0.2.1", "198.51.100.7"]);
+    let mut buffer = Vec::new();
+    let su3 = builder
+        .build(&mut buffer)
+        .expect("Failed to build blocklist feed");
+    assert_eq!(su3.file_type, FileType::TxtGz);
+    assert_eq!(su3.content().unwrap(), &b"192.0.2.1\n198.51.100.7\n"[..]);
+
+    let builder = BlocklistFeedBuilder::new("blocklist@mail.i2p", "1700000000")
+        .with_entry("192.0.2.1\n198.51.100.7");
+    assert!(builder.build(&mut Vec::new()).is_err());
+
+    for feed in [
+        "<html></html>",
+        "<feedback></feedback>",
+        "<html><feed></feed></html>",
+        "<!-- <feed> --><html></html>",
+    ] {
+        let builder = NewsFeedBuilder::new("news@mail.i2p", "1700000000").with_feed(feed);
+        assert!(
+            builder.build(&mut Vec::new()).is_err(),
+            "{feed} is accepted"
+        );
+    }
+    for feed in [
+        "<!-- news --><!DOCTYPE feed>\n<feed/>",
+        "<atom:feed xmlns:atom='http://www.w3.org/2005/Atom'></atom:feed>",
+    ] {
+        let builder = NewsFeedBuilder::new("news@mail.i2p", "1700000000").with_feed(feed);
+        assert!(builder.build(&mut Vec::new()).is_ok(), "{feed} is rejected");
+    }
+}
+
+/// Wrap a router update and reject anything that isn't a ZIP file
+#[test]
+fn build_router_update() {
+    let update = b"PK\x03\x04rest of the archive";
+    let builder = RouterUpdateBuilder::new("zzz@mail.i2p", "2.4.0").with_update(&update[..]);
+    let su3 = builder.build().expect("Failed to build router update");
+    assert_eq!(su3.content_type, ContentType::RouterUpdate);
+    assert_eq!(su3.content_length, update.len() as u64);
+
+    let builder = RouterUpdateBuilder::new("zzz@mail.i2p", "2.4.0").with_update(&b"MZ"[..]);
+    assert!(builder.build().is_err());
+    assert!(RouterUpdateBuilder::new("", "2.4.0")
+        .with_update(&update[..])
+        .build()
+        .is_err());
+}
+
+/// Archive a plugin directory
+#[cfg(feature = "zip")]
+#[test]
+fn build_plugin() {
+    use crate::PluginBuilder;
+    use std::fs;
+
+    let directory = std::env::temp_dir().join(format!("su3-plugin-{}", std::process::id()));
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    fs::write(directory.join("plugin.config"), "name=test\n").unwrap();
+    fs::write(directory.join("lib/test.jar"), "jar").unwrap();
+
+    let builder = PluginBuilder::new("plugin@mail.i2p", "1.0.0").with_directory(&directory);
+    let mut buffer = Vec::new();
+    let su3 = builder.build(&mut buffer).expect("Failed to build plugin");
+    assert_eq!(su3.content_type, ContentType::Plugin);
+    assert_eq!(su3.file_type, FileType::Zip);
+    let archive = zip::ZipArchive::new(io::Cursor::new(su3.raw_content))
+        .expect("Failed to read plugin archive");
+    assert_eq!(archive.len(), 3);
+    assert!(archive.index_for_name("plugin.config").is_some());
+    assert!(archive.index_for_name("lib/test.jar").is_some());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(std::env::temp_dir(), directory.join("lib/outside")).unwrap();
+        let err = builder.build(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        fs::remove_file(directory.join("lib/outside")).unwrap();
+    }
+
+    fs::remove_file(directory.join("plugin.config")).unwrap();
+    assert!(builder.build(&mut Vec::new()).is_err());
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,362 @@
+//! Signature verification with pluggable key resolution
+
+use crate::{Header, HexPreview, SignatureType, Su3, Su3Reader};
+use deku::DekuError;
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    future::Future,
+    hash::BuildHasher,
+    io::{self, Read, Write},
+    path::PathBuf,
+    str::Utf8Error,
+    time::SystemTime,
+};
+
+/// Public key of a signer
+///
+/// The `Debug` representation only shows the length and a short hex preview of the key
+#[derive(Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    /// Signature type the key is used with
+    ///
+    /// `None` in case the provider doesn't know the type (eg. when the key is embedded in a certificate)
+    pub signature_type: Option<SignatureType>,
+
+    /// Encoded key (or certificate), interpreted by the [`SignatureVerifier`]
+    pub data: Vec<u8>,
+}
+
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublicKey")
+            .field("signature_type", &self.signature_type)
+            .field("data", &HexPreview(&self.data))
+            .finish()
+    }
+}
+
+/// Resolves signer IDs to their public keys
+///
+/// Providers are queried on every verification, so keys added to the backing store (eg. a certificate directory,
+/// an HTTPS endpoint or the netDb) are picked up without restarting
+pub trait KeyProvider {
+    /// Return all the public keys the signer might have signed a file with
+    ///
+    /// An unknown signer is not an error and should be reported as an empty list
+    fn public_keys(
+        &self,
+        signer_id: &str,
+    ) -> impl Future<Output = io::Result<Vec<PublicKey>>> + Send;
+}
+
+impl<S> KeyProvider for HashMap<String, Vec<PublicKey>, S>
+where
+    S: BuildHasher + Sync,
+{
+    async fn public_keys(&self, signer_id: &str) -> io::Result<Vec<PublicKey>> {
+        Ok(self.get(signer_id).cloned().unwrap_or_default())
+    }
+}
+
+/// Key provider reading certificates from a directory
+///
+/// Uses the naming scheme of the Java I2P router (eg. `meeh_at_mail.i2p.crt` for the signer `meeh@mail.i2p`).
+/// The files are returned as-is, without a signature type.
+///
+/// The certificates are read with blocking IO through [`CertDirKeyProvider::read_public_keys`].
+/// The [`KeyProvider`] implementation calls it inline, which is acceptable since it reads at most one small
+/// certificate per call. Providers for slow backing stores (eg. network file systems) should offload the read instead
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertDirKeyProvider {
+    path: PathBuf,
+}
+
+impl CertDirKeyProvider {
+    /// Construct a new provider reading from the directory
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self { path: path.into() }
+    }
+
+    /// Read the certificate of the signer (blocking)
+    ///
+    /// Signer IDs that would escape the directory (eg. `../signer` or `a/b`) are treated as unknown
+    ///
+    /// # Errors
+    ///
+    /// Returns an IO error in case the certificate exists but can't be read
+    pub fn read_public_keys(&self, signer_id: &str) -> io::Result<Vec<PublicKey>> {
+        if signer_id.is_empty()
+            || signer_id.starts_with('.')
+            || signer_id.contains(['/', '\\', '\0'])
+        {
+            return Ok(Vec::new());
+        }
+
+        let file_name = format!("{}.crt", signer_id.replace('@', "_at_"));
+        match fs::read(self.path.join(file_name)) {
+            Ok(data) => Ok(vec![PublicKey {
+                signature_type: None,
+                data,
+            }]),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl KeyProvider for CertDirKeyProvider {
+    async fn public_keys(&self, signer_id: &str) -> io::Result<Vec<PublicKey>> {
+        self.read_public_keys(signer_id)
+    }
+}
+
+/// Cryptographic backend checking a signature against a public key
+///
+/// The signed data is fed into an incremental digest, so files never have to be buffered for verification.
+/// The signature is then checked against the finished digest (the data is prehashed, like the Java I2P router does)
+pub trait SignatureVerifier {
+    /// Incremental digest state the signed data is written into
+    type Digest: Write;
+
+    /// Start a new digest for the signature type (eg. SHA-512 for RSA-SHA512-4096)
+    fn digest(&self, signature_type: &SignatureType) -> Self::Digest;
+
+    /// Finish the digest and return its value
+    fn finish(&self, digest: Self::Digest) -> Vec<u8>;
+
+    /// Whether the signature over the digested data was made with the key
+    fn verify(
+        &self,
+        signature_type: &SignatureType,
+        public_key: &PublicKey,
+        digest: &[u8],
+        signature: &[u8],
+    ) -> bool;
+}
+
+/// File whose signed data has been digested, ready to be verified
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestedFile {
+    /// Header of the file
+    pub header: Header,
+
+    /// Digest of the signed data as computed by the [`SignatureVerifier`]
+    pub digest: Vec<u8>,
+
+    /// Signature
+    pub signature: Vec<u8>,
+
+    /// Time the digest has been started at (ie. the start of the verification)
+    pub started_at: SystemTime,
+}
+
+/// Check performed during verification
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "audit",
+    derive(serde::Serialize),
+    serde(crate = "serde", rename_all = "snake_case")
+)]
+pub enum VerificationCheck {
+    /// The signer ID is valid UTF-8
+    SignerId,
+
+    /// The signature length matches the signature type
+    SignatureLength,
+
+    /// The key provider knows at least one key for the signer
+    KeyResolution,
+
+    /// At least one key of the signer matches the signature type
+    SignatureType,
+
+    /// The signature has been made with one of the keys
+    Signature,
+}
+
+/// Verification error
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The signer ID is not valid UTF-8
+    InvalidSignerId(Utf8Error),
+
+    /// Serialising the signed data failed
+    Serialise(DekuError),
+
+    /// Reading or digesting the signed data failed
+    Io(io::Error),
+
+    /// The key provider failed
+    KeyProvider(io::Error),
+
+    /// The key provider doesn't know any key for the signer matching the signature type
+    UnknownSigner,
+
+    /// The signature length doesn't match the signature type
+    InvalidSignatureLength,
+
+    /// The signature doesn't match any of the signer's keys
+    InvalidSignature,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidSignerId(err) => write!(f, "invalid signer ID: {err}"),
+            Self::Serialise(err) => write!(f, "failed to serialise signed data: {err}"),
+            Self::Io(err) => write!(f, "failed to digest signed data: {err}"),
+            Self::KeyProvider(err) => write!(f, "failed to resolve public keys: {err}"),
+            Self::UnknownSigner => f.write_str("no public key known for signer"),
+            Self::InvalidSignatureLength => f.write_str("signature length doesn't match type"),
+            Self::InvalidSignature => f.write_str("invalid signature"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidSignerId(err) => Some(err),
+            Self::Serialise(err) => Some(err),
+            Self::Io(err) | Self::KeyProvider(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Su3<'_> {
+    /// Verify the signature of the file and return the key it was made with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case the keys of the signer can't be resolved or the signature is invalid
+    pub async fn verify<P, V>(&self, provider: &P, verifier: &V) -> Result<PublicKey, VerifyError>
+    where
+        P: KeyProvider,
+        V: SignatureVerifier,
+    {
+        self.digest(verifier)?.verify(provider, verifier).await
+    }
+
+    /// Digest the signed data of the file
+    pub(crate) fn digest<V>(&self, verifier: &V) -> Result<DigestedFile, VerifyError>
+    where
+        V: SignatureVerifier,
+    {
+        let started_at = SystemTime::now();
+        let signed_data = self.signed_data().map_err(VerifyError::Serialise)?;
+        let mut digest = verifier.digest(&self.signature_type);
+        digest.write_all(&signed_data).map_err(VerifyError::Io)?;
+
+        Ok(DigestedFile {
+            header: Header::from(self),
+            digest: verifier.finish(digest),
+            signature: self.raw_signature.to_vec(),
+            started_at,
+        })
+    }
+}
+
+impl<R> Su3Reader<'_, R>
+where
+    R: Read,
+{
+    /// Stream the remaining raw content into the writer (eg. [`io::sink`]),
+    /// verify the signature and return the key it was made with
+    ///
+    /// The progress callback and the cancellation token apply while the content is digested
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case reading fails, the operation has been cancelled,
+    /// the keys of the signer can't be resolved or the signature is invalid
+    pub async fn verify<W, P, V>(
+        self,
+        writer: W,
+        provider: &P,
+        verifier: &V,
+    ) -> Result<PublicKey, VerifyError>
+    where
+        W: Write,
+        P: KeyProvider,
+        V: SignatureVerifier,
+    {
+        self.digest(writer, verifier)
+            .map_err(VerifyError::Io)?
+            .verify(provider, verifier)
+            .await
+    }
+}
+
+impl DigestedFile {
+    /// Verify the signature and return the key it was made with
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in case the keys of the signer can't be resolved or the signature is invalid
+    pub async fn verify<P, V>(&self, provider: &P, verifier: &V) -> Result<PublicKey, VerifyError>
+    where
+        P: KeyProvider,
+        V: SignatureVerifier,
+    {
+        let signer_id = self
+            .header
+            .signer_id()
+            .map_err(VerifyError::InvalidSignerId)?;
+
+        verify_signature(self, signer_id, provider, verifier, |_, _| {}).await
+    }
+}
+
+/// Verify the signature and report the outcome of every check to the recorder
+pub(crate) async fn verify_signature<P, V, C>(
+    file: &DigestedFile,
+    signer_id: &str,
+    provider: &P,
+    verifier: &V,
+    mut record: C,
+) -> Result<PublicKey, VerifyError>
+where
+    P: KeyProvider,
+    V: SignatureVerifier,
+    C: FnMut(VerificationCheck, bool),
+{
+    let signature_type = &file.header.signature_type;
+    let valid_length = file.signature.len() == usize::from(signature_type.length());
+    record(VerificationCheck::SignatureLength, valid_length);
+    if !valid_length {
+        return Err(VerifyError::InvalidSignatureLength);
+    }
+
+    let public_keys = provider.public_keys(signer_id).await;
+    record(
+        VerificationCheck::KeyResolution,
+        matches!(public_keys, Ok(ref public_keys) if !public_keys.is_empty()),
+    );
+    let mut public_keys = public_keys.map_err(VerifyError::KeyProvider)?;
+    if public_keys.is_empty() {
+        return Err(VerifyError::UnknownSigner);
+    }
+
+    public_keys.retain(|public_key| {
+        public_key
+            .signature_type
+            .as_ref()
+            .is_none_or(|key_type| key_type == signature_type)
+    });
+    record(VerificationCheck::SignatureType, !public_keys.is_empty());
+    if public_keys.is_empty() {
+        return Err(VerifyError::UnknownSigner);
+    }
+
+    let public_key = public_keys.into_iter().find(|public_key| {
+        verifier.verify(signature_type, public_key, &file.digest, &file.signature)
+    });
+    record(VerificationCheck::Signature, public_key.is_some());
+
+    public_key.ok_or(VerifyError::InvalidSignature)
+}